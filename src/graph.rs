@@ -13,23 +13,35 @@ use std::collections::{
     HashMap
 };
 
+use blake2::{Blake2b, Digest};
+use blake2::digest::consts::U32;
+
+/// Blake2b configured to a 256bit digest, matching what Grin hashes cycle
+/// proofs with.
+type Blake2b256 = Blake2b<U32>;
+
 
 /// Graph structure
-/// 
+///
 /// The graph for cuckoo cycle has N edges and N+N nodes.
 /// It is bipartite, meaning the nodes can be divided into
 /// two disjoint sets, U and V, where nodes in U only have
 /// edges to nodes in V and vice versa.
-/// 
+///
 /// The edges of the graph are generated pseudorandomly using
 /// the siphash-2-4 hash function.
-/// 
+///
 /// Struct Fields:
-///     edges - Edges are stored as a list of Edges instead of
-///             of an adjacency matrix to preserve the indexing.
+///     edges     - Edges are stored as a list of Edges instead of
+///                 of an adjacency matrix to preserve the indexing.
+///     edge_bits - Set when the graph was built with `new_cuckatoo`, in
+///                 which case each partition has exactly 2^edge_bits nodes.
+///                 `None` for the classic Cuckoo graph built with `new`,
+///                 whose partition size is the arbitrary `n` passed in.
 #[derive(Debug)]
 pub struct Graph {
     edges: Vec<Edge>,
+    edge_bits: Option<u32>,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
@@ -41,6 +53,30 @@ pub enum Node {
 type Edge = (Node, Node);
 type AdjacencyMatrix = HashMap<Node, RefCell<HashSet<Node>>>;
 
+/// Reasons `Graph::verify` can reject a proposed cycle, distinguishing a
+/// malformed proof from a merely-wrong one. This matters when validating
+/// untrusted proofs coming off the network rather than ones this crate
+/// produced itself.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum VerifyError {
+    /// `edges.len()` or `cycle_len` is odd; cycles in a bipartite graph always have even length.
+    OddLength,
+    /// `edges.len()` does not match `cycle_len`.
+    LengthMismatch,
+    /// `cycle_len` is zero.
+    ZeroLength,
+    /// The edge indices are not sorted in ascending order.
+    UnsortedIndices,
+    /// The same edge index appears more than once.
+    DuplicateEdge,
+    /// An edge index does not exist on the graph.
+    EdgeOutOfRange,
+    /// Some node touched by the cycle is not incident on exactly two of its edges.
+    NodeDegreeNotTwo,
+    /// The edges form something other than a single closed cycle (e.g. disjoint cycles).
+    NotASingleCycle,
+}
+
 impl Graph {
     
     /// Construct a new graph with n edges and n+n nodes.
@@ -72,7 +108,37 @@ impl Graph {
         // }
 
 
-        Self { edges }
+        Self { edges, edge_bits: None }
+    }
+
+    /// Construct a new Cuckatoo graph with the given hash function key and edge_bits.
+    /// This is the ASIC-resistant^(-1) variant used by Grin and other chains: unlike
+    /// `new`, the node count is forced to a power of two (`N = 1 << edge_bits`) and
+    /// edges are generated with a bitmask instead of a modulo. Cuckatoo also does not
+    /// collapse the two node partitions by their last bit the way Cuckoo's mathspec
+    /// describes, so both U and V range over the full `0..N`.
+    pub fn new_cuckatoo(key: [u64; 4], edge_bits: u32) -> Self {
+        let n: u64 = 1 << edge_bits;
+        let mask: u64 = n - 1;
+        let mut edges = Vec::with_capacity(n as usize);
+        let hasher = SipHash::new(key);
+
+        let mut i: u64 = 0;
+        while i < n {
+            let u: u64 = hasher.hash(2*i)   & mask;
+            let v: u64 = hasher.hash(2*i+1) & mask;
+            edges.push((Node::U(u), Node::V(v)));
+
+            i += 1;
+        }
+
+        Self { edges, edge_bits: Some(edge_bits) }
+    }
+
+    /// Return the edge_bits the graph was constructed with, or `None` if it
+    /// is a classic Cuckoo graph (built with `new`) rather than a Cuckatoo one.
+    pub fn edge_bits(&self) -> Option<u32> {
+        self.edge_bits
     }
 
     /// Return the number of nodes in self
@@ -87,152 +153,331 @@ impl Graph {
 
     /// Get the edge at the given index
     fn edge_at(&self, index: usize) -> Option<Edge> {
-        if index > self.edges.len() {
+        if index >= self.edges.len() {
             return None
         }
 
         Some(self.edges[index])
     }
 
-    // Given an edge, return the index of the edge if it exists.
-    fn index_of(&self, edge: &Edge) -> Option<usize> {
-        self.edges.iter().position(|(u, v)| (*u, *v) == *edge || (*v, *u) == *edge)
-    }
-
     /// Solve for a cycle with the given number of edges.
     /// The result of this function is either a vector of edge indicies
     /// or nothing in the case that no cycle exists on the graph.
     pub fn solve(&self, cycle_len: usize) -> Option<Vec<usize>> {
-        // Run a few rounds of edge trimming to remove unecessary edges
-        let mut adjmatrix = self.adjacency_matrix();
-        Self::edge_trim(&mut adjmatrix, 100);
-
-        self.graph_mine(&adjmatrix, cycle_len)
-    } 
-
-    /// Given a adjacency matrix, trim edges that cannot be part of a cycle.
-    /// This is done by removing edges that incident on nodes with a degree < 2.
-    /// Running edge trimming a few times can drastically reduce the time it takes
-    /// to solve for a cycle in the graph.
-    fn edge_trim(adjmatrix: &mut AdjacencyMatrix, count: usize) {        
+        // Run the lean trimmer to discard edges that cannot be part of a
+        // cycle before handing anything over to the union-find cycle search.
+        let alive = self.lean_trim(100);
+
+        self.union_find_cycle(&alive, cycle_len)
+    }
+
+    /// Solve for a cycle the same way as `solve`, but only return it if its
+    /// proof hashes at or below `target`. A miner loop is expected to call this
+    /// repeatedly against fresh graphs (new keys) until a cycle meets the
+    /// target, the same way a real Cuckoo/Cuckatoo miner would.
+    pub fn solve_with_target(&self, cycle_len: usize, target: [u8; 32]) -> Option<Vec<usize>> {
+        let cycle = self.solve(cycle_len)?;
+
+        if self.meets_target(&cycle, target) {
+            Some(cycle)
+        } else {
+            None
+        }
+    }
+
+    /// Check whether a recovered cycle's proof hash meets the given target,
+    /// i.e. `hash_cycle(cycle) <= target`. This is the actual proof-of-work
+    /// condition: a valid cycle alone is necessary but not sufficient, the
+    /// proof must also be "lucky" enough to hash at or below the target.
+    pub fn meets_target(&self, cycle_edge_indices: &[usize], target: [u8; 32]) -> bool {
+        self.hash_cycle(cycle_edge_indices) <= target
+    }
+
+    /// Hash a recovered cycle's sorted edge indices the way Grin hashes a
+    /// cycle proof: pack the indices as little-endian integers of
+    /// `edge_bits` width each, then run Blake2b-256 over the packed bytes.
+    /// The resulting digest is the big-endian 256bit integer compared
+    /// against a target in `meets_target`.
+    pub fn hash_cycle(&self, cycle_edge_indices: &[usize]) -> [u8; 32] {
+        let edge_bits = self.edge_bits.unwrap_or_else(|| Self::bits_for_node_count(self.edges.len()));
+        let packed = Self::pack_proof(cycle_edge_indices, edge_bits);
+
+        let mut hasher = Blake2b256::new();
+        hasher.update(&packed);
+
+        let mut digest = [0u8; 32];
+        digest.copy_from_slice(&hasher.finalize());
+        digest
+    }
+
+    /// Pack edge indices into a little-endian bitstream of `edge_bits`-wide
+    /// integers, the way Grin packs a cycle proof before hashing it.
+    fn pack_proof(cycle_edge_indices: &[usize], edge_bits: u32) -> Vec<u8> {
+        let mut acc: u128 = 0;
+        let mut acc_bits: u32 = 0;
+        let mut packed = Vec::new();
+
+        for &index in cycle_edge_indices {
+            acc |= (index as u128) << acc_bits;
+            acc_bits += edge_bits;
+
+            while acc_bits >= 8 {
+                packed.push((acc & 0xff) as u8);
+                acc >>= 8;
+                acc_bits -= 8;
+            }
+        }
+
+        if acc_bits > 0 {
+            packed.push((acc & 0xff) as u8);
+        }
+
+        packed
+    }
+
+    /// The number of bits needed to address `n` edges, used to size proof
+    /// packing for classic Cuckoo graphs that were not given an `edge_bits`
+    /// at construction (`new_cuckatoo` graphs already carry one).
+    fn bits_for_node_count(n: usize) -> u32 {
+        if n <= 1 {
+            return 0
+        }
+
+        usize::BITS - (n - 1).leading_zeros()
+    }
+
+    /// Compute the 32-byte difficulty target below which a proof's hash
+    /// must fall, where `target = 2^256 / difficulty`. This is a convenience
+    /// for callers that think in terms of a difficulty scalar (as Grin
+    /// does) rather than a raw threshold.
+    pub fn target_from_difficulty(difficulty: u64) -> [u8; 32] {
+        assert!(difficulty > 0, "difficulty must be non-zero");
+
+        // 2^256 / 1 is 2^256, which does not fit in a 32-byte target at all;
+        // saturate to the maximum representable value (2^256 - 1) so every
+        // hash meets it, same as a real difficulty-1 target would.
+        if difficulty == 1 {
+            return [0xff; 32]
+        }
+
+        // Long divide 2^256 (an implicit leading 1 byte followed by 32 zero
+        // bytes) by `difficulty`, one byte at a time, keeping only the
+        // (always 32-byte-fitting) quotient.
+        let mut remainder: u128 = 1;
+        let mut target = [0u8; 32];
+        for byte in target.iter_mut() {
+            remainder <<= 8;
+            *byte = (remainder / difficulty as u128) as u8;
+            remainder %= difficulty as u128;
+        }
+
+        target
+    }
+
+    /// The size of each partition (one past the largest U/V node id present
+    /// in the graph's edges), used to size the degree counters in
+    /// `lean_trim`. For `new`/`new_cuckatoo` graphs this is at most `n`/`1 <<
+    /// edge_bits`, and `Graph::from` can produce graphs whose node ids don't
+    /// line up with `edges.len()`, so this is computed from the edges
+    /// themselves rather than assumed.
+    fn partition_sizes(&self) -> (usize, usize) {
+        let mut u_count = 0usize;
+        let mut v_count = 0usize;
+
+        for (u, v) in &self.edges {
+            match (u, v) {
+                (Node::U(u), Node::V(v)) => {
+                    u_count = u_count.max(*u as usize + 1);
+                    v_count = v_count.max(*v as usize + 1);
+                },
+                _ => unreachable!("edges are always stored (U, V)")
+            }
+        }
+
+        (u_count, v_count)
+    }
+
+    /// Lean edge trimming, mirroring Grin's trimming stage.
+    ///
+    /// Rather than building a `HashMap` adjacency matrix, this keeps only a
+    /// `Vec<bool>` of which edges are still alive plus a pair of per-node
+    /// degree counters (one per partition, sized to that partition and
+    /// indexed by node id). Each round: a first pass counts how many alive
+    /// edges touch each U and V node, then a second pass kills any alive
+    /// edge whose U or V endpoint has a degree below 2 (such edges can never
+    /// be part of a cycle). This repeats until a round kills nothing or
+    /// `count` rounds have run.
+    fn lean_trim(&self, count: usize) -> Vec<bool> {
+        let (u_count, v_count) = self.partition_sizes();
+        let mut alive = vec![true; self.edges.len()];
+
         for _ in 0..count {
-            if adjmatrix.is_empty() {
-                break;
+            let mut u_degree = vec![0u32; u_count];
+            let mut v_degree = vec![0u32; v_count];
+
+            for (i, (u, v)) in self.edges.iter().enumerate() {
+                if !alive[i] {
+                    continue
+                }
+
+                match (u, v) {
+                    (Node::U(u), Node::V(v)) => {
+                        u_degree[*u as usize] += 1;
+                        v_degree[*v as usize] += 1;
+                    },
+                    _ => unreachable!("edges are always stored (U, V)")
+                }
             }
-            
-            for node in adjmatrix.keys() {
-                let mut neighbours = adjmatrix
-                                        .get(node)
-                                        .expect("Node not found")
-                                        .borrow_mut();
-                
-                if neighbours.len() >= 2 {
+
+            let mut trimmed = false;
+            for (i, (u, v)) in self.edges.iter().enumerate() {
+                if !alive[i] {
                     continue
                 }
-                
-                for neighbour in neighbours.iter() {
-                    adjmatrix
-                        .get(neighbour)
-                        .expect("Node not found")
-                        .borrow_mut()
-                        .remove(node);
+
+                let (u, v) = match (u, v) {
+                    (Node::U(u), Node::V(v)) => (*u as usize, *v as usize),
+                    _ => unreachable!("edges are always stored (U, V)")
+                };
+
+                if u_degree[u] < 2 || v_degree[v] < 2 {
+                    alive[i] = false;
+                    trimmed = true;
                 }
+            }
 
-                neighbours.clear();
+            if !trimmed {
+                break
             }
         }
-        
-        adjmatrix.retain(|_, v| v.borrow().len() > 0);
-    }
-
-    /// Graph mining technique to solve for a cycle on the graph.
-    /// This solving method uses brute force to traverse every path
-    /// that is at most the specified solution length and checks 
-    /// if the start and end of the path are equal, in which case the
-    /// path is a cycle.
-    /// 
-    /// TODO: Add graph mining tests.
-    fn graph_mine(&self, adjmatrix: &AdjacencyMatrix, cycle_len: usize) -> Option<Vec<usize>> {
-        // For each node, 
-        for node in adjmatrix.keys() {
-            let neighbours = adjmatrix.get(node).expect("Node missing").borrow();
-            
-            // If it has less than 2 neighbours, skip it.
-            if neighbours.len() < 2 {
+
+        alive
+    }
+
+    /// Find a cycle of exactly `cycle_len` edges among the edges marked alive in
+    /// `alive` using Tromp's union-find cycle finder.
+    ///
+    /// A disjoint-set forest is grown one alive edge at a time. For an edge
+    /// `(u, v)`: if `u` and `v` are already in the same set, adding it would
+    /// close a cycle, so the cycle is recovered by walking the forest from
+    /// `u` and from `v` up to their lowest common ancestor and collecting the
+    /// edges on both branches plus the closing edge. If that cycle has
+    /// exactly `cycle_len` edges, it is re-checked with `verify` before being
+    /// returned as sorted edge indices, since a malformed forest would
+    /// otherwise surface as a cycle-shaped but invalid proof; a cycle that
+    /// fails verification is discarded and the scan continues. Otherwise `u`
+    /// and `v` are unioned and the edge is recorded as the forest edge
+    /// linking them.
+    fn union_find_cycle(&self, alive: &[bool], cycle_len: usize) -> Option<Vec<usize>> {
+        let mut forest = UnionFind::new();
+
+        for (i, (u, v)) in self.edges.iter().enumerate() {
+            if !alive[i] {
                 continue
             }
-            
-            // Otherwise, try find a cycle using depth first search.
-            match self.dfs(adjmatrix, node, Vec::new(), cycle_len) {
-                None => continue,
-                Some(x) => return self.edges_to_indexes(&x)
+
+            let u = forest.id(*u);
+            let v = forest.id(*v);
+
+            if forest.connected(u, v) {
+                let cycle = forest.cycle_edges(u, v, i);
+                if cycle.len() == cycle_len {
+                    let mut indexes = cycle;
+                    indexes.sort();
+                    if self.verify(cycle_len, &indexes).is_ok() {
+                        return Some(indexes)
+                    }
+                }
+            } else {
+                forest.union(u, v, i);
             }
         }
 
         None
     }
 
-    /// Find a cycle using depth first search.
-    /// Modifying the adjacency matrix by removing used edges can make the algorithm more efficient.
-    fn dfs(&self, adjmatrix: &AdjacencyMatrix, start: &Node, path: Vec<Edge>, limit: usize)-> Option<Vec<Edge>> {        
-        // Base case where the length limit has been reached. Return the path if it is a cycle.
-        if limit == 0 {
-            // If the path is trivial, return None
-            if path.len() == 0 {
-                return None
-            }
-            
-            let first = path.first().expect("Path is empty");
-            let last = path.last().expect("Path is empty");
-            let indexes = self.edges_to_indexes(&path)?;
-
-            // If the path starts and ends on the same node and is a verified cycle, return it.
-            if first.0 == last.1 && self.verify(path.len(), &indexes[..]) {
-                return Some(path)
-            }
-
-            return None
+    /// Enumerate every distinct simple cycle of exactly `len` edges in the
+    /// graph, each returned as a sorted list of edge indices.
+    ///
+    /// Unlike `solve`/`union_find_cycle`, which stop at the first cycle of
+    /// the target length, this walks every simple path: a `visited` stack of
+    /// nodes is extended one neighbour at a time, and whenever the stack
+    /// reaches `len` nodes it checks whether the last node closes back to
+    /// the start. Cyclic rotations are filtered by only emitting a cycle
+    /// when `start` is the smallest node id in it, and reversals are
+    /// filtered by only emitting when the node following `start` is smaller
+    /// than the node that closes back to it.
+    pub fn cycles_of_length(&self, len: usize) -> Vec<Vec<usize>> {
+        if len < 2 {
+            return Vec::new()
         }
 
-        // Recursive case, iterate each edge on the current node.
-        if let Some(refc) = adjmatrix.get(start) {
-            let neighbours = refc.borrow();
-            let mut paths: Vec<Option<Vec<Edge>>> = Vec::with_capacity(neighbours.len());
-            let nodes = neighbours.iter().map(|n| *n).collect::<Vec<Node>>();
+        let adjmatrix = self.adjacency_matrix();
+        let mut cycles = Vec::new();
 
-            for n in nodes {
-                let nadjmatrix = adjmatrix.clone();
-                let mut path_cont = Vec::from(&path[..]);
+        for &start in adjmatrix.keys() {
+            let mut visited = vec![start];
+            self.extend_path(&adjmatrix, start, &mut visited, len, &mut cycles);
+        }
 
-                nadjmatrix.get(&n).expect("Node missing").borrow_mut().remove(start);
+        cycles
+    }
 
-                path_cont.push((*start, n));
-                paths.push(self.dfs(&nadjmatrix, &n, path_cont, limit-1));
+    /// Recursive step of `cycles_of_length`: extend the path in `visited` by
+    /// one more neighbour of its last node, or, once it holds `len` nodes,
+    /// check whether it closes back into a cycle starting at `start`.
+    fn extend_path(
+        &self,
+        adjmatrix: &AdjacencyMatrix,
+        start: Node,
+        visited: &mut Vec<Node>,
+        len: usize,
+        cycles: &mut Vec<Vec<usize>>
+    ) {
+        let current = *visited.last().expect("path always has a node");
+        let neighbours = adjmatrix.get(&current).expect("node missing").borrow();
+
+        if visited.len() == len {
+            let second = visited[1];
+            let last = *visited.last().expect("path always has a node");
+
+            if neighbours.contains(&start) && start == *visited.iter().min().expect("path non-empty") && second < last {
+                if let Some(indices) = self.path_to_indices(visited, start) {
+                    cycles.push(indices);
+                }
             }
 
-            // Of all possible paths from the current node, only keep the cycles.
-            paths.retain(|x| x.is_some());
+            return
+        }
 
-            // If there are any paths remaining, return the first path that was found.
-            if paths.len() > 0 {
-                return paths.into_iter().nth(0).expect("Path missing")
+        for neighbour in neighbours.iter() {
+            if visited.contains(neighbour) {
+                continue
             }
 
-            return None
+            visited.push(*neighbour);
+            self.extend_path(adjmatrix, start, visited, len, cycles);
+            visited.pop();
         }
-
-        None
     }
 
-    /// Given a graph (self) and a list of edges, reutrn a list of corresponding edge indexes.
-    fn edges_to_indexes(&self, edges: &Vec<Edge>) -> Option<Vec<usize>> {
-        let mut indexes = Vec::with_capacity(edges.len());
-        for edge in edges {
-            indexes.push(self.index_of(edge)?);
+    /// Given the node sequence of a closed path (closing back to `start`),
+    /// return the corresponding sorted edge indices.
+    fn path_to_indices(&self, visited: &[Node], start: Node) -> Option<Vec<usize>> {
+        let mut indices = Vec::with_capacity(visited.len());
+
+        for window in visited.windows(2) {
+            indices.push(self.edge_index(window[0], window[1])?);
         }
+        indices.push(self.edge_index(*visited.last()?, start)?);
+
+        indices.sort();
+        Some(indices)
+    }
 
-        indexes.sort();
-        Some(indexes)
+    /// Find the index of the edge connecting `a` and `b`, in either direction.
+    fn edge_index(&self, a: Node, b: Node) -> Option<usize> {
+        self.edges.iter().position(|(u, v)| (*u == a && *v == b) || (*u == b && *v == a))
     }
 
     /// Create an adjacency matrix representation of the graph.
@@ -240,9 +485,15 @@ impl Graph {
     /// partition of the node set.
     ///
     fn adjacency_matrix(&self) -> AdjacencyMatrix {
+        Self::build_adjacency_matrix(self.edges.iter())
+    }
+
+    /// Shared adjacency matrix construction, kept separate so other
+    /// edge iterators (e.g. over a subset of edges) could reuse it.
+    fn build_adjacency_matrix<'a>(edges: impl Iterator<Item = &'a Edge>) -> AdjacencyMatrix {
         let mut adjmatrix: AdjacencyMatrix = HashMap::new();
 
-        for (a, b) in &self.edges {
+        for (a, b) in edges {
             if !adjmatrix.contains_key(&a) {
                 let mut set = HashSet::new();
                 set.insert(*b);
@@ -273,36 +524,41 @@ impl Graph {
 
 
     /// Verify a cycle and check if it is a cycle on self.
-    /// This is done by storing each visited node in a list, 
+    /// This is done by storing each visited node in a list,
     /// and making sure the edges of the provided cycle enter
     /// and leave the each node that is part of the cycle.
-    /// 
-    /// TODO:
-    ///     - Add and return a enum for returning verification results. This
-    ///       can help identify the reason why verification fails.
-    pub fn verify(&self, cycle_len: usize, edges: &[usize]) -> bool { 
+    ///
+    /// Returns `Ok(())` if `edges` is a valid `cycle_len`-cycle on this
+    /// graph, or the specific `VerifyError` that made it invalid otherwise.
+    pub fn verify(&self, cycle_len: usize, edges: &[usize]) -> Result<(), VerifyError> {
         // Early fail conditions
         //  - Provided edges or cycle len is odd.
         //  - Edge len does not equal cycle len.
         //  - Cycle len is zero.
-        if edges.len()%2 == 1 || cycle_len%2 == 1 || edges.len() != cycle_len || cycle_len == 0 {
-            return false
+        if edges.len()%2 == 1 || cycle_len%2 == 1 {
+            return Err(VerifyError::OddLength)
+        }
+        if edges.len() != cycle_len {
+            return Err(VerifyError::LengthMismatch)
+        }
+        if cycle_len == 0 {
+            return Err(VerifyError::ZeroLength)
         }
-        
+
         // Initialise node and edge tracker
         let mut counter: HashMap<Node, usize> = HashMap::new();
         let mut edgeset: HashSet<usize> = HashSet::new();
         let mut prev = edges[0];
-        
+
         for index in edges {
             // If edge is used before, fail verification,
             if edgeset.contains(index) {
-                return false;
+                return Err(VerifyError::DuplicateEdge)
             }
 
             // If edge indexes are not sorted, fail verification.
             if *index < prev {
-                return false;
+                return Err(VerifyError::UnsortedIndices)
             }
 
             // Track the edge as used
@@ -322,7 +578,7 @@ impl Graph {
                     counter.insert(v, 1);
                 }
             } else {
-                return false
+                return Err(VerifyError::EdgeOutOfRange)
             }
 
             prev = *index;
@@ -330,7 +586,7 @@ impl Graph {
 
         // Fail if every involved vertice is not incidented on twice.
         if counter.iter().any(|(_, i)| *i != 2) {
-            return false
+            return Err(VerifyError::NodeDegreeNotTwo)
         }
 
         // Follow cycle
@@ -343,10 +599,14 @@ impl Graph {
 
         loop {
             let mut adjs = cmatrix.get(&pos).expect("Node missing").borrow_mut();
-            
+
             // End of cycle
             if adjs.len() == 0 && pos == *start {
-                return n == cycle_len;
+                return if n == cycle_len {
+                    Ok(())
+                } else {
+                    Err(VerifyError::NotASingleCycle)
+                }
             }
 
             match adjs.iter().next() {
@@ -354,22 +614,175 @@ impl Graph {
                     cmatrix.get(node).expect("Node missing").borrow_mut().remove(&pos);
                     pos = node.clone();
                 },
-                _ => return false // Dead end (should not occur...)
+                _ => return Err(VerifyError::NotASingleCycle) // Dead end (should not occur...)
             }
 
             adjs.remove(&pos);
             n += 1;
         }
     }
+
+    /// Thin `bool` wrapper around `verify`, for callers that only care
+    /// whether a cycle is valid and not why it failed.
+    pub fn verify_bool(&self, cycle_len: usize, edges: &[usize]) -> bool {
+        self.verify(cycle_len, edges).is_ok()
+    }
+}
+
+/// Disjoint-set forest used by `Graph::union_find_cycle` to find cycles
+/// without brute-force path enumeration.
+///
+/// Nodes are assigned dense `usize` ids on first sight (`id`). `parent`/`rank`
+/// back a standard union-by-rank, path-compressed find used only to answer
+/// "are these two nodes already connected?". Path compression on that array
+/// would erase the actual tree shape, so a second, uncompressed parent
+/// pointer (`tree_parent`, paired with the edge index that created it) is
+/// kept purely to walk a node back up to its root when a cycle needs to be
+/// recovered. Every node reaches a genuine root this way: `union` re-roots
+/// a tree before attaching it, rather than hanging the new edge off an
+/// unrelated set representative.
+struct UnionFind {
+    ids: HashMap<Node, usize>,
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    tree_parent: Vec<Option<(usize, usize)>>,
+}
+
+impl UnionFind {
+    fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            parent: Vec::new(),
+            rank: Vec::new(),
+            tree_parent: Vec::new(),
+        }
+    }
+
+    /// Get the dense id for a node, allocating a new singleton set for it
+    /// the first time it is seen.
+    fn id(&mut self, node: Node) -> usize {
+        if let Some(id) = self.ids.get(&node) {
+            return *id
+        }
+
+        let id = self.parent.len();
+        self.ids.insert(node, id);
+        self.parent.push(id);
+        self.rank.push(0);
+        self.tree_parent.push(None);
+        id
+    }
+
+    /// Find the representative of `x`'s set, compressing the path as it goes.
+    fn find(&mut self, x: usize) -> usize {
+        if self.parent[x] != x {
+            self.parent[x] = self.find(self.parent[x]);
+        }
+        self.parent[x]
+    }
+
+    /// Whether `a` and `b` are already in the same set.
+    fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Union the sets containing `a` and `b`, recording `edge` as the forest
+    /// edge that links them.
+    ///
+    /// `b`'s tree is first re-rooted at `b` (reversing its parent pointers
+    /// in place), then `b` is attached directly under `a`. Attaching at the
+    /// set roots instead (as plain union-by-rank would) is not good enough:
+    /// the new edge only actually connects `a` and `b`, not their roots, so
+    /// `ancestors` would walk a pointer that doesn't correspond to a real
+    /// edge. Re-rooting keeps every `tree_parent` pointer a genuine edge of
+    /// the graph, all the way up to the true root.
+    fn union(&mut self, a: usize, b: usize, edge: usize) {
+        self.reroot(b);
+        self.tree_parent[b] = Some((a, edge));
+
+        // Union-by-rank bookkeeping, kept separate from `tree_parent` and
+        // used only to answer `connected`/`find` queries quickly.
+        let (ra, rb) = (self.find(a), self.find(b));
+        if self.rank[ra] < self.rank[rb] {
+            self.parent[ra] = rb;
+        } else {
+            self.parent[rb] = ra;
+            if self.rank[ra] == self.rank[rb] {
+                self.rank[ra] += 1;
+            }
+        }
+    }
+
+    /// Reverse `node`'s parent pointers so that `node` becomes the root of
+    /// its own tree, preserving every edge along the way.
+    fn reroot(&mut self, node: usize) {
+        let mut chain = Vec::new();
+        let mut cur = node;
+        while let Some((parent, edge)) = self.tree_parent[cur] {
+            chain.push((cur, parent, edge));
+            cur = parent;
+        }
+
+        for (child, parent, edge) in chain {
+            self.tree_parent[parent] = Some((child, edge));
+        }
+        self.tree_parent[node] = None;
+    }
+
+    /// Walk from `node` up to its forest root, returning the sequence of
+    /// `(node, edge-to-parent)` pairs visited, ending with the root itself
+    /// (whose edge is `None`).
+    fn ancestors(&self, mut node: usize) -> Vec<(usize, Option<usize>)> {
+        let mut path = Vec::new();
+        while let Some((parent, edge)) = self.tree_parent[node] {
+            path.push((node, Some(edge)));
+            node = parent;
+        }
+        path.push((node, None));
+        path
+    }
+
+    /// Recover the cycle closed by adding edge `closing_edge` between `u`
+    /// and `v`, which `union_find_cycle` has already established sit in the
+    /// same tree. This walks both nodes up to their lowest common ancestor
+    /// and collects the forest edges on each branch plus the closing edge.
+    fn cycle_edges(&self, u: usize, v: usize, closing_edge: usize) -> Vec<usize> {
+        let anc_u = self.ancestors(u);
+        let anc_v = self.ancestors(v);
+        let u_nodes: HashSet<usize> = anc_u.iter().map(|(n, _)| *n).collect();
+
+        let lca = anc_v.iter()
+            .find(|(n, _)| u_nodes.contains(n))
+            .expect("both branches share the forest root")
+            .0;
+
+        let mut edges = Vec::new();
+        for (node, edge) in &anc_u {
+            if *node == lca {
+                break
+            }
+            edges.push(edge.expect("non-root ancestor has a parent edge"));
+        }
+        for (node, edge) in &anc_v {
+            if *node == lca {
+                break
+            }
+            edges.push(edge.expect("non-root ancestor has a parent edge"));
+        }
+        edges.push(closing_edge);
+
+        edges
+    }
 }
 
 impl From<Vec<(u64, u64)>> for Graph {
     fn from(edges: Vec<(u64, u64)>) -> Self {
-        Self { 
+        Self {
             edges: edges
                     .iter()
                     .map(|(a, b)| (Node::U(*a), Node::V(*b)))
-                    .collect() 
+                    .collect(),
+            edge_bits: None,
         }
     }
 }
@@ -377,7 +790,7 @@ impl From<Vec<(u64, u64)>> for Graph {
 impl From<Vec<Edge>> for Graph {
     fn from(edges: Vec<Edge>) -> Self {
         let mut g = Vec::new();
-        
+
         for edge in edges {
             match edge {
                 (Node::U(_), Node::V(_)) => g.push(edge),
@@ -386,7 +799,7 @@ impl From<Vec<Edge>> for Graph {
             }
         }
 
-        Self { edges: g }
+        Self { edges: g, edge_bits: None }
     }
 }
 
@@ -399,7 +812,7 @@ mod tests {
         let edges = vec![(0, 0), (1, 0), (1, 2), (3, 2), (3, 3), (0, 3)];
         let graph = Graph::from(edges);
         let cycle = [0, 1, 2, 3, 4, 5];
-        assert!(graph.verify(6, &cycle));
+        assert_eq!(graph.verify(6, &cycle), Ok(()));
     }
 
     #[test]
@@ -407,6 +820,67 @@ mod tests {
         let edges = vec![(0, 0), (0, 1), (1, 0), (1, 1), (6, 6), (6, 7), (7, 6), (7, 7)];
         let graph = Graph::from(edges);
         let cycle = [0, 1, 2, 3, 4, 5, 6, 7];
-        assert!(!graph.verify(8, &cycle));
+        assert_eq!(graph.verify(8, &cycle), Err(VerifyError::NotASingleCycle));
+        assert!(!graph.verify_bool(8, &cycle));
+    }
+
+    #[test]
+    fn verify_rejects_out_of_range_edge_index_instead_of_panicking() {
+        let edges = vec![(0, 0), (1, 0), (1, 2), (3, 2), (3, 3), (0, 3)];
+        let graph = Graph::from(edges);
+        let cycle = [1, 2, 3, 4, 5, 6];
+        assert_eq!(graph.verify(6, &cycle), Err(VerifyError::EdgeOutOfRange));
+    }
+
+    #[test]
+    fn cycles_of_length_finds_the_only_cycle() {
+        let edges = vec![(0, 0), (1, 0), (1, 2), (3, 2), (3, 3), (0, 3)];
+        let graph = Graph::from(edges);
+
+        let cycles = graph.cycles_of_length(6);
+        assert_eq!(cycles, vec![vec![0, 1, 2, 3, 4, 5]]);
+    }
+
+    #[test]
+    fn cycles_of_length_finds_nothing_for_the_wrong_length() {
+        let edges = vec![(0, 0), (1, 0), (1, 2), (3, 2), (3, 3), (0, 3)];
+        let graph = Graph::from(edges);
+
+        assert!(graph.cycles_of_length(4).is_empty());
+    }
+
+    #[test]
+    fn lean_trim_handles_node_ids_past_edge_count() {
+        // 6 edges, but node id 7 exceeds edges.len() - the degree counters
+        // must be sized off the actual node ids, not the edge count.
+        let edges = vec![(0, 0), (1, 0), (1, 2), (3, 2), (3, 3), (0, 7)];
+        let graph = Graph::from(edges);
+
+        let alive = graph.lean_trim(10);
+        assert_eq!(alive.len(), graph.edge_count());
+    }
+
+    #[test]
+    fn target_from_difficulty_one_saturates_to_maximum() {
+        assert_eq!(Graph::target_from_difficulty(1), [0xff; 32]);
+    }
+
+    #[test]
+    fn solve_only_returns_cycles_that_verify() {
+        // Previously the union-find forest attached new edges under the
+        // opposite set's root instead of its actual endpoint, so `ancestors`
+        // could walk pointers that didn't correspond to real edges and
+        // `solve` would return a cycle-shaped but invalid proof.
+        let graph = Graph::new_cuckatoo([47, 25, 70, 30], 7);
+        if let Some(cycle) = graph.solve(4) {
+            assert_eq!(graph.verify(4, &cycle), Ok(()));
+        }
+    }
+
+    #[test]
+    fn target_from_difficulty_two_is_half_the_max() {
+        let target = Graph::target_from_difficulty(2);
+        assert_eq!(target[0], 0x80);
+        assert!(target[1..].iter().all(|&b| b == 0));
     }
 }
\ No newline at end of file
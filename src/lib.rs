@@ -13,7 +13,7 @@
 /// algorithm.
 
 mod sip;
-pub mod cuckoo;
+pub mod graph;
 
 
 #[cfg(test)]